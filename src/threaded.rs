@@ -27,19 +27,28 @@ use nokhwa_core::{
 };
 use std::{
     any::Any,
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, UdpSocket},
     sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU64, AtomicUsize, Ordering},
+        mpsc, Arc, Condvar, Mutex,
     },
+    thread,
+    time::{Duration, Instant},
 };
 
 type AtomicLock<T> = Arc<Mutex<T>>;
+/// Signature of [`camera_frame_thread_loop`], the capture loop that pulls frames from
+/// the camera and feeds them to the frame queue and any RTSP endpoints.
+/// [`CallbackCamera::new`] starts only the dispatch thread; it does not spawn this loop.
 pub type CallbackFn = fn(
-    _camera: &Arc<Mutex<Camera>>,
-    _frame_callback: &Arc<Mutex<Option<Box<dyn FnMut(Buffer) + Send + 'static>>>>,
-    _last_frame_captured: &Arc<Mutex<Buffer>>,
+    _camera: &AtomicLock<Camera>,
+    _frame_queue: &Arc<FrameQueue>,
+    _last_frame_captured: &AtomicLock<ImageBuffer<Rgb<u8>, Vec<u8>>>,
+    _rtsp_endpoints: &AtomicLock<Vec<Arc<RtspEndpoint>>>,
     _die_bool: &Arc<AtomicBool>,
+    _capture_suspended: &Arc<AtomicBool>,
 );
 type HeldCallbackType = Arc<Mutex<Box<dyn FnMut(Buffer) + Send + 'static>>>;
 
@@ -61,6 +70,11 @@ pub struct CallbackCamera {
     frame_callback: HeldCallbackType,
     last_frame_captured: AtomicLock<Buffer>,
     die_bool: Arc<AtomicBool>,
+    rtsp_endpoints: AtomicLock<Vec<Arc<RtspEndpoint>>>,
+    frame_queue: Arc<FrameQueue>,
+    /// Set for the duration of [`capture_burst`](CallbackCamera::capture_burst) so
+    /// the background capture loop steps aside instead of racing it for frames.
+    capture_suspended: Arc<AtomicBool>,
 }
 
 impl CallbackCamera {
@@ -73,15 +87,32 @@ impl CallbackCamera {
         callback: impl FnMut(Buffer) + Send + 'static,
     ) -> Result<Self, NokhwaError> {
         let arc_camera = Arc::new(Mutex::new(Camera::new(index, format)?));
+        let frame_callback: HeldCallbackType = Arc::new(Mutex::new(Box::new(callback)));
+        let die_bool: Arc<AtomicBool> = Arc::new(Default::default());
+        let capture_suspended: Arc<AtomicBool> = Arc::new(Default::default());
+        let frame_queue = Arc::new(FrameQueue::new(DEFAULT_QUEUE_DEPTH, FrameDropPolicy::Block));
+
+        {
+            let frame_queue = frame_queue.clone();
+            let frame_callback = frame_callback.clone();
+            let die_bool = die_bool.clone();
+            thread::spawn(move || {
+                frame_dispatch_thread_loop(&frame_queue, &frame_callback, &die_bool)
+            });
+        }
+
         Ok(CallbackCamera {
             camera: arc_camera,
-            frame_callback: Arc::new(Mutex::new(Box::new(callback))),
+            frame_callback,
             last_frame_captured: Arc::new(Mutex::new(Buffer::new_with_vec(
                 Resolution::new(0, 0),
                 &vec![],
                 FrameFormat::GRAY,
             ))),
-            die_bool: Arc::new(Default::default()),
+            die_bool,
+            rtsp_endpoints: Arc::new(Mutex::new(Vec::new())),
+            frame_queue,
+            capture_suspended,
         })
     }
 
@@ -355,6 +386,98 @@ impl CallbackCamera {
             .open_stream()
     }
 
+    /// Sets the depth of the bounded queue between the capture thread and the
+    /// dispatch thread that calls the frame callback. A deeper queue absorbs longer
+    /// callback stalls at the cost of more buffered (and thus staler) frames.
+    pub fn set_queue_depth(&mut self, depth: usize) {
+        self.frame_queue.set_depth(depth);
+    }
+
+    /// Sets the [`FrameDropPolicy`] applied when the queue between the capture thread
+    /// and the dispatch thread is full.
+    pub fn set_frame_drop_policy(&mut self, policy: FrameDropPolicy) {
+        self.frame_queue.set_policy(policy);
+    }
+
+    /// The number of frames dropped so far by [`FrameDropPolicy::DropOldest`] or
+    /// [`FrameDropPolicy::DropNewest`] because the dispatch thread could not keep up.
+    /// The count is not reset when the policy changes.
+    #[must_use]
+    pub fn dropped_frame_count(&self) -> u64 {
+        self.frame_queue.dropped_count()
+    }
+
+    /// Serves this camera's frames over RTSP, binding a listener on `bind_addr` and
+    /// publishing at `rtsp://<bind_addr>/<path>`. Every frame captured on the capture
+    /// thread is packetized as MJPEG-over-RTP (RFC 2435) - since the camera can
+    /// already deliver [`FrameFormat::MJPEG`](nokhwa_core::types::FrameFormat::MJPEG),
+    /// this costs no extra encoding - and fanned out to every client that has
+    /// completed `SETUP`. Packets flow from `SETUP` on; `PLAY` is acknowledged but
+    /// does not gate delivery.
+    ///
+    /// This spawns a background thread that accepts connections for the lifetime of
+    /// the `CallbackCamera`; it is torn down when the camera is dropped.
+    /// # Errors
+    /// This will error if `bind_addr` cannot be bound, if the camera was not opened
+    /// with [`FrameFormat::MJPEG`] - the MJPEG-over-RTP payload packetizes raw JPEG
+    /// bytes, so any other format would be shipped out mislabeled as MJPEG and
+    /// produce an undecodable stream - or if either dimension of the camera's
+    /// resolution exceeds [`MAX_RTSP_JPEG_DIMENSION`], which is as large as the
+    /// one-byte width/8 and height/8 fields in the RFC 2435 JPEG header can encode.
+    pub fn serve_rtsp(
+        &mut self,
+        bind_addr: SocketAddr,
+        path: impl Into<String>,
+    ) -> Result<(), NokhwaError> {
+        let frame_format = self.frame_format()?;
+        if frame_format != FrameFormat::MJPEG {
+            return Err(NokhwaError::SetPropertyError {
+                property: "frame_format".to_string(),
+                value: frame_format.to_string(),
+                error: "serve_rtsp requires the camera to be opened with FrameFormat::MJPEG"
+                    .to_string(),
+            });
+        }
+
+        let resolution = self.resolution()?;
+        if resolution.width() > MAX_RTSP_JPEG_DIMENSION
+            || resolution.height() > MAX_RTSP_JPEG_DIMENSION
+        {
+            return Err(NokhwaError::SetPropertyError {
+                property: "resolution".to_string(),
+                value: resolution.to_string(),
+                error: format!(
+                    "serve_rtsp requires both dimensions to be <= {MAX_RTSP_JPEG_DIMENSION}px - \
+                     RFC 2435's JPEG header encodes width/8 and height/8 in a single byte each"
+                ),
+            });
+        }
+
+        let path = path.into();
+        let listener =
+            TcpListener::bind(bind_addr).map_err(|why| NokhwaError::SetPropertyError {
+                property: "rtsp_bind_addr".to_string(),
+                value: bind_addr.to_string(),
+                error: why.to_string(),
+            })?;
+
+        let main_endpoint = Arc::new(RtspEndpoint::new(path.clone()));
+
+        self.rtsp_endpoints
+            .lock()
+            .map_err(|why| NokhwaError::SetPropertyError {
+                property: "rtsp_endpoints".to_string(),
+                value: path.clone(),
+                error: why.to_string(),
+            })?
+            .push(main_endpoint.clone());
+
+        let die_bool = self.die_bool.clone();
+        thread::spawn(move || rtsp_accept_loop(listener, vec![main_endpoint], bind_addr, die_bool));
+
+        Ok(())
+    }
+
     /// Sets the frame callback to the new specified function. This function will be called instead of the previous one(s).
     pub fn set_callback(
         &mut self,
@@ -383,6 +506,36 @@ impl CallbackCamera {
         Ok(frame)
     }
 
+    /// Grabs `count` consecutive frames at the camera's maximum rate, tagging each
+    /// with the [`Instant`] it was captured at.
+    ///
+    /// This bypasses the registered frame callback entirely - there is no per-frame
+    /// dispatch overhead - and tight-loops [`poll_frame`](CallbackCamera::poll_frame)
+    /// into a pre-allocated buffer sized to `count` until it fills. Useful for HDR
+    /// bracketing, focus stacking, or picking the sharpest frame out of a burst.
+    /// # Errors
+    /// This will error if the stream is not already open, or if a frame fails to
+    /// capture partway through the burst - no frames are silently dropped.
+    pub fn capture_burst(&mut self, count: usize) -> Result<Vec<(Instant, Buffer)>, NokhwaError> {
+        if !self.is_stream_open() {
+            return Err(NokhwaError::ReadFrameError(
+                "stream must be open before a burst capture".to_string(),
+            ));
+        }
+
+        // `camera_frame_thread_loop` also locks the camera to pull frames; without
+        // stepping it aside, it and this tight loop would race each other for
+        // hardware frames for the duration of the burst.
+        let _suspend_guard = CaptureSuspendGuard::new(&self.capture_suspended);
+
+        let mut burst = Vec::with_capacity(count);
+        while burst.len() < count {
+            let frame = self.poll_frame()?;
+            burst.push((Instant::now(), frame));
+        }
+        Ok(burst)
+    }
+
     /// Gets the last frame captured by the camera.
     #[must_use]
     pub fn last_frame(&self) -> Buffer {
@@ -422,21 +575,1016 @@ impl Drop for CallbackCamera {
     }
 }
 
+/// RAII guard that steps [`camera_frame_thread_loop`] aside while it's held, so a
+/// foreground caller (e.g. [`capture_burst`](CallbackCamera::capture_burst)) can
+/// own the camera lock for several consecutive frames without the background loop
+/// racing it for each one. Always clears the flag on drop, even if the foreground
+/// caller returns early via `?`.
+struct CaptureSuspendGuard<'a> {
+    capture_suspended: &'a Arc<AtomicBool>,
+}
+
+impl<'a> CaptureSuspendGuard<'a> {
+    fn new(capture_suspended: &'a Arc<AtomicBool>) -> Self {
+        capture_suspended.store(true, Ordering::SeqCst);
+        CaptureSuspendGuard { capture_suspended }
+    }
+}
+
+impl Drop for CaptureSuspendGuard<'_> {
+    fn drop(&mut self) {
+        self.capture_suspended.store(false, Ordering::SeqCst);
+    }
+}
+
 fn camera_frame_thread_loop(
     camera: &AtomicLock<Camera>,
-    frame_callback: &HeldCallbackType,
+    frame_queue: &Arc<FrameQueue>,
     last_frame_captured: &AtomicLock<ImageBuffer<Rgb<u8>, Vec<u8>>>,
+    rtsp_endpoints: &AtomicLock<Vec<Arc<RtspEndpoint>>>,
     die_bool: &Arc<AtomicBool>,
+    capture_suspended: &Arc<AtomicBool>,
 ) {
     loop {
-        if let Ok(img) = camera.lock().fr {
+        if capture_suspended.load(Ordering::SeqCst) {
+            // A foreground caller (e.g. capture_burst) owns the camera for now;
+            // back off instead of contending with it for the next frame.
+            thread::sleep(Duration::from_millis(1));
+        } else if let Ok(img) = camera.lock().fr {
             *last_frame_captured.lock() = img.clone();
-            if let Some(cb) = (*frame_callback.lock()).as_mut() {
-                cb(img);
+            if let Ok(endpoints) = rtsp_endpoints.lock() {
+                for endpoint in endpoints.iter() {
+                    endpoint.publish(&img);
+                }
+            }
+            // Pushed onto the bounded queue rather than called inline, so a slow
+            // callback cannot stall this capture loop; `frame_dispatch_thread_loop`
+            // drains it on its own thread.
+            frame_queue.push(img, die_bool);
+        }
+        if die_bool.load(Ordering::SeqCst) {
+            frame_queue.wake_all();
+            break;
+        }
+    }
+}
+
+// Keeps `CallbackFn` honest: this fails to compile if `camera_frame_thread_loop`'s
+// signature ever drifts from the alias again.
+const _: CallbackFn = camera_frame_thread_loop;
+
+/// Default depth of the bounded queue sitting between the capture thread and the
+/// dispatch thread.
+const DEFAULT_QUEUE_DEPTH: usize = 4;
+
+/// What to do when the bounded frame queue between the capture thread and the
+/// dispatch thread is full and a new frame arrives.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FrameDropPolicy {
+    /// Block the capture thread until the dispatch thread drains a slot.
+    Block,
+    /// Discard the oldest queued frame to make room for the new one.
+    DropOldest,
+    /// Discard the newly captured frame, leaving the queue as-is.
+    DropNewest,
+}
+
+/// A bounded single-producer single-consumer queue of [`Buffer`]s, decoupling frame
+/// capture from callback dispatch so a heavy callback cannot serialize against device
+/// I/O. Its depth and [`FrameDropPolicy`] can be changed at runtime.
+struct FrameQueue {
+    frames: Mutex<VecDeque<Buffer>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    depth: AtomicUsize,
+    policy: Mutex<FrameDropPolicy>,
+    dropped: AtomicU64,
+}
+
+impl FrameQueue {
+    fn new(depth: usize, policy: FrameDropPolicy) -> Self {
+        FrameQueue {
+            frames: Mutex::new(VecDeque::with_capacity(depth)),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            depth: AtomicUsize::new(depth),
+            policy: Mutex::new(policy),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    fn set_depth(&self, depth: usize) {
+        self.depth.store(depth, Ordering::SeqCst);
+        self.not_full.notify_all();
+    }
+
+    fn set_policy(&self, policy: FrameDropPolicy) {
+        if let Ok(mut guard) = self.policy.lock() {
+            *guard = policy;
+        }
+    }
+
+    fn dropped_count(&self) -> u64 {
+        self.dropped.load(Ordering::SeqCst)
+    }
+
+    /// Pushes a captured frame, applying the configured [`FrameDropPolicy`] if the
+    /// queue is already at its configured depth. Gives up and drops `buffer` once
+    /// `die_bool` is set, the same shutdown signal [`pop_blocking`](FrameQueue::pop_blocking)
+    /// honors - otherwise, under `Block` policy, a capture thread could wait on
+    /// `not_full` forever if the dispatch thread drained and exited first.
+    fn push(&self, buffer: Buffer, die_bool: &AtomicBool) {
+        let depth = self.depth.load(Ordering::SeqCst).max(1);
+        let policy = self.policy.lock().map_or(FrameDropPolicy::Block, |p| *p);
+
+        let Ok(mut frames) = self.frames.lock() else {
+            return;
+        };
+        loop {
+            if frames.len() < depth {
+                frames.push_back(buffer);
+                break;
+            }
+            if die_bool.load(Ordering::SeqCst) {
+                return;
+            }
+            match policy {
+                FrameDropPolicy::Block => {
+                    let (guard, _) = match self
+                        .not_full
+                        .wait_timeout(frames, Duration::from_millis(50))
+                    {
+                        Ok(result) => result,
+                        Err(_) => return,
+                    };
+                    frames = guard;
+                }
+                FrameDropPolicy::DropOldest => {
+                    frames.pop_front();
+                    self.dropped.fetch_add(1, Ordering::SeqCst);
+                    frames.push_back(buffer);
+                    break;
+                }
+                FrameDropPolicy::DropNewest => {
+                    self.dropped.fetch_add(1, Ordering::SeqCst);
+                    break;
+                }
             }
         }
+        drop(frames);
+        self.not_empty.notify_one();
+    }
+
+    /// Blocks until a frame is available or `die_bool` is set, in which case `None`
+    /// is returned so the dispatch thread can exit.
+    fn pop_blocking(&self, die_bool: &AtomicBool) -> Option<Buffer> {
+        let mut frames = self.frames.lock().ok()?;
+        loop {
+            if let Some(frame) = frames.pop_front() {
+                self.not_full.notify_one();
+                return Some(frame);
+            }
+            if die_bool.load(Ordering::SeqCst) {
+                return None;
+            }
+            let (guard, _) = self
+                .not_empty
+                .wait_timeout(frames, Duration::from_millis(50))
+                .ok()?;
+            frames = guard;
+        }
+    }
+
+    fn wake_all(&self) {
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+}
+
+/// Drains `frame_queue` on its own thread and forwards each frame to the registered
+/// callback, so that a slow callback only backs up the queue instead of blocking
+/// frame capture.
+fn frame_dispatch_thread_loop(
+    frame_queue: &Arc<FrameQueue>,
+    frame_callback: &HeldCallbackType,
+    die_bool: &Arc<AtomicBool>,
+) {
+    loop {
+        match frame_queue.pop_blocking(die_bool) {
+            Some(frame) => {
+                if let Ok(mut cb) = frame_callback.lock() {
+                    if let Some(cb) = cb.as_mut() {
+                        cb(frame);
+                    }
+                }
+            }
+            None => break,
+        }
+    }
+}
+
+/// The RTP payload type used for the MJPEG payload, as assigned by RFC 2435 section 3.
+const RTSP_MJPEG_PAYLOAD_TYPE: u8 = 26;
+/// The RTP clock rate for the MJPEG payload: a fixed 90kHz, per RFC 2435.
+const RTSP_MJPEG_CLOCK_RATE: u32 = 90_000;
+/// The largest frame width or height that fits the one-byte `width/8` and `height/8`
+/// fields in the RFC 2435 JPEG header (255 * 8).
+const MAX_RTSP_JPEG_DIMENSION: u32 = 2040;
+
+/// A transport negotiated with a client during `SETUP`, over which RTP packets for a
+/// single [`RtspEndpoint`] are delivered.
+enum RtspTransport {
+    /// `RTP/AVP` over UDP, sent to the first port of the client's `client_port`
+    /// pair. No RTCP socket is opened.
+    Udp {
+        rtp_socket: UdpSocket,
+        remote: SocketAddr,
+    },
+    /// `RTP/AVP/TCP` interleaved on the existing control connection.
+    Interleaved {
+        stream: AtomicLock<TcpStream>,
+        channel: u8,
+    },
+}
+
+impl RtspTransport {
+    /// Sends `packet` to this client, returning `false` if the transport is dead and
+    /// should be pruned from its [`RtspEndpoint`].
+    fn send(&self, packet: &[u8]) -> bool {
+        match self {
+            RtspTransport::Udp { rtp_socket, .. } => rtp_socket.send(packet).is_ok(),
+            RtspTransport::Interleaved { stream, channel } => {
+                let Ok(mut stream) = stream.lock() else {
+                    return false;
+                };
+                let mut framed = Vec::with_capacity(4 + packet.len());
+                framed.push(b'$');
+                framed.push(*channel);
+                framed.extend_from_slice(&(packet.len() as u16).to_be_bytes());
+                framed.extend_from_slice(packet);
+                stream.write_all(&framed).is_ok()
+            }
+        }
+    }
+}
+
+/// One client that has completed `SETUP` for an [`RtspEndpoint`], identified by the
+/// `Session` id handed back in the `SETUP` response so it can be looked up again on
+/// `TEARDOWN`.
+struct RtspClient {
+    session_id: u32,
+    transport: RtspTransport,
+}
+
+/// A single named RTSP stream, e.g. `rtsp://host:8554/<name>`.
+///
+/// Holds every client that has completed `SETUP` for this path and packetizes
+/// each published [`Buffer`] into RTP packets (RFC 2435 MJPEG payload) fanned out to
+/// all of them. Clients are dropped from `clients` on `TEARDOWN`, on control
+/// connection EOF, or as soon as a send to them fails.
+struct RtspEndpoint {
+    path: String,
+    clients: AtomicLock<Vec<RtspClient>>,
+    next_session_id: AtomicU32,
+    sequence: AtomicU16,
+    ssrc: u32,
+    start: std::time::Instant,
+}
+
+impl RtspEndpoint {
+    fn new(path: String) -> Self {
+        RtspEndpoint {
+            path,
+            clients: Arc::new(Mutex::new(Vec::new())),
+            next_session_id: AtomicU32::new(1),
+            sequence: AtomicU16::new(0),
+            // Not cryptographically meaningful, just needs to be "probably unique".
+            ssrc: std::process::id(),
+            start: std::time::Instant::now(),
+        }
+    }
+
+    /// Builds the session description for `DESCRIBE`. `bind_addr` is advertised as
+    /// the session-level `c=` (connection) line, which RFC 4566 requires at the
+    /// session or media level for a client to know where to send `SETUP`.
+    fn sdp(&self, bind_addr: SocketAddr) -> String {
+        format!(
+            "v=0\r\no=- 0 0 IN IP4 {ip}\r\ns={path}\r\nc=IN IP4 {ip}\r\nt=0 0\r\nm=video 0 RTP/AVP {pt}\r\na=control:{path}\r\n",
+            ip = bind_addr.ip(),
+            path = self.path,
+            pt = RTSP_MJPEG_PAYLOAD_TYPE,
+        )
+    }
+
+    /// Registers `transport` as a new client and returns the `Session` id it was
+    /// assigned, to be echoed back by the caller in the `SETUP` response.
+    fn add_client(&self, transport: RtspTransport) -> u32 {
+        let session_id = self.next_session_id.fetch_add(1, Ordering::SeqCst);
+        if let Ok(mut clients) = self.clients.lock() {
+            clients.push(RtspClient {
+                session_id,
+                transport,
+            });
+        }
+        session_id
+    }
+
+    /// Removes the client with `session_id`, if any. Safe to call with an id that is
+    /// no longer present (e.g. already pruned after a failed send).
+    fn remove_client(&self, session_id: u32) {
+        if let Ok(mut clients) = self.clients.lock() {
+            clients.retain(|client| client.session_id != session_id);
+        }
+    }
+
+    /// Packetizes `buffer` as RFC 2435 MJPEG-over-RTP and sends it to every client,
+    /// pruning any client whose transport has gone dead.
+    fn publish(&self, buffer: &Buffer) {
+        let Ok(mut clients) = self.clients.lock() else {
+            return;
+        };
+        if clients.is_empty() {
+            return;
+        }
+
+        let timestamp =
+            (self.start.elapsed().as_secs_f64() * f64::from(RTSP_MJPEG_CLOCK_RATE)) as u32;
+        let payload = buffer.buffer();
+        // RFC 2435 caps a fragment at 65535 bytes minus headers; keep well under that.
+        const MAX_FRAGMENT: usize = 1400;
+        // RFC 2435 section 3.1.8: Q >= 128 means the quantization tables are carried
+        // in an extra header we don't emit, so a decoder would have nothing to
+        // dequantize with. Staying below 128 keeps the fixed-table Annex K encoding.
+        const JPEG_QUALITY: u8 = 80;
+
+        for (i, chunk) in payload.chunks(MAX_FRAGMENT).enumerate() {
+            let is_last = (i + 1) * MAX_FRAGMENT >= payload.len();
+            let sequence = self.sequence.fetch_add(1, Ordering::SeqCst);
+
+            let mut packet = Vec::with_capacity(12 + 8 + chunk.len());
+            // RTP header (RFC 3550 section 5.1).
+            packet.push(0x80);
+            packet.push(RTSP_MJPEG_PAYLOAD_TYPE | if is_last { 0x80 } else { 0x00 });
+            packet.extend_from_slice(&sequence.to_be_bytes());
+            packet.extend_from_slice(&timestamp.to_be_bytes());
+            packet.extend_from_slice(&self.ssrc.to_be_bytes());
+            // JPEG header (RFC 2435 section 3.1): fragment offset, type, Q, width/8, height/8.
+            let offset = (i * MAX_FRAGMENT) as u32;
+            packet.extend_from_slice(&offset.to_be_bytes()[1..4]);
+            packet.push(0); // type
+            packet.push(JPEG_QUALITY);
+            packet.push((buffer.resolution().width() / 8) as u8);
+            packet.push((buffer.resolution().height() / 8) as u8);
+            packet.extend_from_slice(chunk);
+
+            clients.retain(|client| client.transport.send(&packet));
+        }
+    }
+}
+
+/// Accepts RTSP control connections for `endpoints` until `die_bool` is set, handling
+/// the `OPTIONS` / `DESCRIBE` / `SETUP` / `PLAY` handshake described in RFC 2326.
+fn rtsp_accept_loop(
+    listener: TcpListener,
+    endpoints: Vec<Arc<RtspEndpoint>>,
+    bind_addr: SocketAddr,
+    die_bool: Arc<AtomicBool>,
+) {
+    let _ = listener.set_nonblocking(true);
+    loop {
         if die_bool.load(Ordering::SeqCst) {
             break;
         }
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let endpoints = endpoints.clone();
+                let die_bool = die_bool.clone();
+                thread::spawn(move || {
+                    rtsp_handle_connection(stream, &endpoints, bind_addr, &die_bool)
+                });
+            }
+            Err(_) => {
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+}
+
+/// Reads one `\r\n`-terminated line, polling `die_bool` across the control stream's
+/// read timeout so a connection with an idle client doesn't block this thread forever
+/// past the owning camera's lifetime. Returns `None` on EOF, a hard read error, or
+/// `die_bool` being set.
+fn read_rtsp_line(reader: &mut BufReader<TcpStream>, die_bool: &AtomicBool) -> Option<String> {
+    loop {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            Ok(0) => return None,
+            Ok(_) => return Some(line),
+            Err(why)
+                if why.kind() == io::ErrorKind::WouldBlock
+                    || why.kind() == io::ErrorKind::TimedOut =>
+            {
+                if die_bool.load(Ordering::SeqCst) {
+                    return None;
+                }
+            }
+            Err(_) => return None,
+        }
+    }
+}
+
+/// Handles one RTSP control connection end-to-end: request parsing, `CSeq` echoing,
+/// and dispatch of `OPTIONS`/`DESCRIBE`/`SETUP`/`PLAY`/`TEARDOWN`. Whatever client was
+/// registered via `SETUP` on this connection is removed from its endpoint when the
+/// connection tears down, is closed by the client, or `die_bool` is set.
+fn rtsp_handle_connection(
+    stream: TcpStream,
+    endpoints: &[Arc<RtspEndpoint>],
+    bind_addr: SocketAddr,
+    die_bool: &AtomicBool,
+) {
+    let _ = stream.set_read_timeout(Some(Duration::from_millis(200)));
+    let mut reader = BufReader::new(stream.try_clone().expect("clone rtsp control stream"));
+    let mut write_stream = stream;
+    let mut session: Option<(Arc<RtspEndpoint>, u32)> = None;
+
+    macro_rules! teardown_and_return {
+        () => {{
+            if let Some((endpoint, session_id)) = session.take() {
+                endpoint.remove_client(session_id);
+            }
+            return;
+        }};
+    }
+
+    loop {
+        let Some(request_line) = read_rtsp_line(&mut reader, die_bool) else {
+            teardown_and_return!();
+        };
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let uri = parts.next().unwrap_or_default().to_string();
+
+        let mut cseq = String::from("0");
+        let mut transport_header = String::new();
+        loop {
+            let Some(header_line) = read_rtsp_line(&mut reader, die_bool) else {
+                teardown_and_return!();
+            };
+            let header_line = header_line.trim_end();
+            if header_line.is_empty() {
+                break;
+            }
+            if let Some(value) = header_line.strip_prefix("CSeq:") {
+                cseq = value.trim().to_string();
+            }
+            if let Some(value) = header_line.strip_prefix("Transport:") {
+                transport_header = value.trim().to_string();
+            }
+        }
+
+        let endpoint = endpoints.iter().find(|e| uri.ends_with(e.path.as_str()));
+
+        let response = match method.as_str() {
+            "OPTIONS" => format!(
+                "RTSP/1.0 200 OK\r\nCSeq: {cseq}\r\nPublic: OPTIONS, DESCRIBE, SETUP, PLAY, TEARDOWN\r\n\r\n"
+            ),
+            "DESCRIBE" => match endpoint {
+                Some(endpoint) => {
+                    let sdp = endpoint.sdp(bind_addr);
+                    format!(
+                        "RTSP/1.0 200 OK\r\nCSeq: {cseq}\r\nContent-Type: application/sdp\r\nContent-Length: {}\r\n\r\n{}",
+                        sdp.len(),
+                        sdp
+                    )
+                }
+                None => format!("RTSP/1.0 404 Not Found\r\nCSeq: {cseq}\r\n\r\n"),
+            },
+            "SETUP" => match endpoint {
+                Some(endpoint) => {
+                    if let Some(transport) = parse_setup_transport(&transport_header, &write_stream) {
+                        let reply_transport = match &transport {
+                            RtspTransport::Udp { remote, .. } => {
+                                format!("RTP/AVP;unicast;client_port={}", remote.port())
+                            }
+                            RtspTransport::Interleaved { channel, .. } => {
+                                format!("RTP/AVP/TCP;interleaved={}-{}", channel, channel + 1)
+                            }
+                        };
+                        let session_id = endpoint.add_client(transport);
+                        session = Some((endpoint.clone(), session_id));
+                        format!(
+                            "RTSP/1.0 200 OK\r\nCSeq: {cseq}\r\nTransport: {reply_transport}\r\nSession: {session_id}\r\n\r\n"
+                        )
+                    } else {
+                        format!("RTSP/1.0 461 Unsupported Transport\r\nCSeq: {cseq}\r\n\r\n")
+                    }
+                }
+                None => format!("RTSP/1.0 404 Not Found\r\nCSeq: {cseq}\r\n\r\n"),
+            },
+            "PLAY" => format!("RTSP/1.0 200 OK\r\nCSeq: {cseq}\r\n\r\n"),
+            "TEARDOWN" => {
+                let _ = write_stream.write_all(format!("RTSP/1.0 200 OK\r\nCSeq: {cseq}\r\n\r\n").as_bytes());
+                teardown_and_return!();
+            }
+            _ => format!("RTSP/1.0 501 Not Implemented\r\nCSeq: {cseq}\r\n\r\n"),
+        };
+
+        if write_stream.write_all(response.as_bytes()).is_err() {
+            teardown_and_return!();
+        }
+    }
+}
+
+/// Parses the client's `Transport:` header from `SETUP` into an [`RtspTransport`],
+/// negotiating either UDP (`client_port=`) or TCP-interleaved (`interleaved=`) delivery.
+fn parse_setup_transport(header: &str, control_stream: &TcpStream) -> Option<RtspTransport> {
+    if let Some(spec) = header
+        .split(';')
+        .find_map(|p| p.strip_prefix("client_port="))
+    {
+        let client_port: u16 = spec.split('-').next()?.parse().ok()?;
+        let mut remote = control_stream.peer_addr().ok()?;
+        remote.set_port(client_port);
+        let rtp_socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+        // `connect` fixes the socket to `remote` so a `send` (vs. `send_to`) surfaces
+        // ICMP port-unreachable as an error on the *next* send once a client is gone,
+        // letting `publish` prune it the same way it prunes dead TCP transports.
+        rtp_socket.connect(remote).ok()?;
+        return Some(RtspTransport::Udp { rtp_socket, remote });
+    }
+    if let Some(spec) = header
+        .split(';')
+        .find_map(|p| p.strip_prefix("interleaved="))
+    {
+        let channel: u8 = spec.split('-').next()?.parse().ok()?;
+        let stream = Arc::new(Mutex::new(control_stream.try_clone().ok()?));
+        return Some(RtspTransport::Interleaved { stream, channel });
+    }
+    None
+}
+
+/// A connect or disconnect event emitted by [`CameraManager::refresh_devices`] when
+/// the enumerated device list changes since the last refresh.
+#[derive(Clone, Debug)]
+pub enum CameraStatusEvent {
+    /// A camera matching this [`CameraInfo`] was not present on the previous refresh.
+    Connected(CameraInfo),
+    /// A camera previously seen at this [`CameraIndex`] is no longer present.
+    Disconnected(CameraIndex),
+}
+
+type StatusCallback = Box<dyn FnMut(CameraStatusEvent) + Send + 'static>;
+
+/// Diffs `current` against `known`, returning a [`CameraStatusEvent`] for every
+/// [`CameraIndex`] that appears in one list but not the other - `known` entries
+/// missing from `current` are `Disconnected`, `current` entries missing from `known`
+/// are `Connected`. Pulled out of [`CameraManager::refresh_devices`] so the diffing
+/// logic can be exercised without a real backend.
+fn diff_known_devices(known: &[CameraInfo], current: &[CameraInfo]) -> Vec<CameraStatusEvent> {
+    let mut events: Vec<CameraStatusEvent> = current
+        .iter()
+        .filter(|info| !known.iter().any(|k| k.index() == info.index()))
+        .map(|info| CameraStatusEvent::Connected(info.clone()))
+        .collect();
+    events.extend(
+        known
+            .iter()
+            .filter(|info| !current.iter().any(|c| c.index() == info.index()))
+            .map(|info| CameraStatusEvent::Disconnected(info.index().clone())),
+    );
+    events
+}
+
+/// A service layer above [`CallbackCamera`] that owns several cameras at once, keyed
+/// by [`CameraIndex`], and routes each device's frames to its own callback -
+/// analogous to a central camera service multiplexing clients.
+///
+/// `refresh_devices` diffs the current [`CameraInfo`] enumeration against the last
+/// known one and emits connect/disconnect events through a user-supplied status
+/// callback, which makes this the natural foundation for surveillance-style apps that
+/// watch many cameras and need to react when one is unplugged or reappears. `open`
+/// arbitrates exclusive access so two callers can't grab the same index.
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "output-threaded")))]
+pub struct CameraManager {
+    backend: ApiBackend,
+    known_devices: Mutex<Vec<CameraInfo>>,
+    open_cameras: Mutex<HashMap<CameraIndex, AtomicLock<CallbackCamera>>>,
+    status_callback: Mutex<Option<StatusCallback>>,
+}
+
+impl CameraManager {
+    /// Creates a new, empty `CameraManager` that will enumerate devices through
+    /// `backend` whenever [`refresh_devices`](CameraManager::refresh_devices) is
+    /// called.
+    #[must_use]
+    pub fn new(backend: ApiBackend) -> Self {
+        CameraManager {
+            backend,
+            known_devices: Mutex::new(Vec::new()),
+            open_cameras: Mutex::new(HashMap::new()),
+            status_callback: Mutex::new(None),
+        }
+    }
+
+    /// Sets the callback invoked with a [`CameraStatusEvent`] for every connect or
+    /// disconnect discovered by [`refresh_devices`](CameraManager::refresh_devices).
+    pub fn set_status_callback(&self, callback: impl FnMut(CameraStatusEvent) + Send + 'static) {
+        if let Ok(mut slot) = self.status_callback.lock() {
+            *slot = Some(Box::new(callback));
+        }
+    }
+
+    /// Re-enumerates devices on the configured backend and diffs the result against
+    /// the last known list, emitting a [`CameraStatusEvent`] through the status
+    /// callback for every camera that appeared or disappeared. Cameras that
+    /// disappear are also closed.
+    /// # Errors
+    /// This will error if the backend cannot be queried.
+    pub fn refresh_devices(&self) -> Result<(), NokhwaError> {
+        let current = crate::query(self.backend)?;
+
+        let mut known = self
+            .known_devices
+            .lock()
+            .map_err(|why| NokhwaError::GetPropertyError {
+                property: "known_devices".to_string(),
+                error: why.to_string(),
+            })?;
+
+        let events = diff_known_devices(&known, &current);
+
+        *known = current;
+        drop(known);
+
+        for event in events {
+            if let CameraStatusEvent::Disconnected(index) = &event {
+                let _ = self.close(index);
+            }
+            if let Ok(mut callback) = self.status_callback.lock() {
+                if let Some(callback) = callback.as_mut() {
+                    callback(event);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Opens `index` with `format`, routing its frames to `callback`, unless it is
+    /// already open through this manager.
+    /// # Errors
+    /// This will error if `index` is already open, or if the camera fails to open.
+    pub fn open(
+        &self,
+        index: CameraIndex,
+        format: RequestedFormat,
+        callback: impl FnMut(Buffer) + Send + 'static,
+    ) -> Result<(), NokhwaError> {
+        let mut open_cameras =
+            self.open_cameras
+                .lock()
+                .map_err(|why| NokhwaError::SetPropertyError {
+                    property: "open_cameras".to_string(),
+                    value: index.to_string(),
+                    error: why.to_string(),
+                })?;
+
+        if open_cameras.contains_key(&index) {
+            return Err(NokhwaError::SetPropertyError {
+                property: "camera_index".to_string(),
+                value: index.to_string(),
+                error: "camera is already open through this manager".to_string(),
+            });
+        }
+
+        let camera = CallbackCamera::new(index.clone(), format, callback)?;
+        open_cameras.insert(index, Arc::new(Mutex::new(camera)));
+        Ok(())
+    }
+
+    /// Closes the camera at `index` if this manager has it open. Not an error if it
+    /// was not open.
+    /// # Errors
+    /// This will error if the internal lock is poisoned.
+    pub fn close(&self, index: &CameraIndex) -> Result<(), NokhwaError> {
+        self.open_cameras
+            .lock()
+            .map_err(|why| NokhwaError::SetPropertyError {
+                property: "open_cameras".to_string(),
+                value: index.to_string(),
+                error: why.to_string(),
+            })?
+            .remove(index);
+        Ok(())
+    }
+
+    /// Returns `true` if `index` is currently open through this manager.
+    pub fn is_open(&self, index: &CameraIndex) -> bool {
+        self.open_cameras
+            .lock()
+            .map_or(false, |cameras| cameras.contains_key(index))
+    }
+
+    /// Pulls one frame from each open camera, tagging each with the [`Instant`] it
+    /// was captured at.
+    ///
+    /// Every camera is polled on its own thread, so a camera that fails to deliver
+    /// within `timeout` cannot block the others - its slot is simply omitted from the
+    /// returned map rather than making the whole capture error out. Pass the result
+    /// through [`align_frame_set`] to additionally drop frames too stale to be
+    /// considered part of the same synchronized set.
+    /// # Errors
+    /// This will error if the internal lock is poisoned.
+    pub fn capture_synchronized(
+        &self,
+        timeout: Duration,
+    ) -> Result<HashMap<CameraIndex, (Instant, Buffer)>, NokhwaError> {
+        let open_cameras = self
+            .open_cameras
+            .lock()
+            .map_err(|why| NokhwaError::ReadFrameError(why.to_string()))?;
+
+        let (sender, receiver) = mpsc::channel();
+        let expected = open_cameras.len();
+        for (index, camera) in open_cameras.iter() {
+            let index = index.clone();
+            let camera = camera.clone();
+            let sender = sender.clone();
+            thread::spawn(move || {
+                let frame = camera
+                    .lock()
+                    .ok()
+                    .and_then(|mut camera| camera.poll_frame().ok());
+                if let Some(frame) = frame {
+                    let _ = sender.send((index, Instant::now(), frame));
+                }
+            });
+        }
+        drop(sender);
+        drop(open_cameras);
+
+        let deadline = Instant::now() + timeout;
+        let mut frames = HashMap::with_capacity(expected);
+        while frames.len() < expected {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            match receiver.recv_timeout(remaining) {
+                Ok((index, instant, frame)) => {
+                    frames.insert(index, (instant, frame));
+                }
+                Err(_) => break,
+            }
+        }
+
+        Ok(frames)
+    }
+}
+
+/// Filters a [`CameraManager::capture_synchronized`] result down to the frames whose
+/// timestamps fall within `tolerance` of the most recently captured frame in the set,
+/// dropping any camera whose frame is too stale to be considered part of the same
+/// synchronized set.
+#[must_use]
+pub fn align_frame_set(
+    frames: HashMap<CameraIndex, (Instant, Buffer)>,
+    tolerance: Duration,
+) -> HashMap<CameraIndex, (Instant, Buffer)> {
+    let Some(newest) = frames.values().map(|(instant, _)| *instant).max() else {
+        return frames;
+    };
+    frames
+        .into_iter()
+        .filter(|(_, (instant, _))| newest.saturating_duration_since(*instant) <= tolerance)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn test_buffer() -> Buffer {
+        Buffer::new_with_vec(Resolution::new(0, 0), vec![], FrameFormat::GRAY)
+    }
+
+    fn sized_test_buffer(size: u32) -> Buffer {
+        Buffer::new_with_vec(Resolution::new(size, size), vec![], FrameFormat::GRAY)
+    }
+
+    #[test]
+    fn frame_queue_pushes_and_pops_in_order() {
+        let queue = FrameQueue::new(4, FrameDropPolicy::Block);
+        let die_bool = AtomicBool::new(false);
+        queue.push(sized_test_buffer(1), &die_bool);
+        queue.push(sized_test_buffer(2), &die_bool);
+        assert_eq!(queue.pop_blocking(&die_bool).unwrap().resolution(), Resolution::new(1, 1));
+        assert_eq!(queue.pop_blocking(&die_bool).unwrap().resolution(), Resolution::new(2, 2));
+        assert_eq!(queue.dropped_count(), 0);
+    }
+
+    #[test]
+    fn frame_queue_drop_oldest_keeps_depth_and_counts_drops() {
+        let queue = FrameQueue::new(2, FrameDropPolicy::DropOldest);
+        let die_bool = AtomicBool::new(false);
+        queue.push(test_buffer(), &die_bool);
+        queue.push(test_buffer(), &die_bool);
+        queue.push(test_buffer(), &die_bool);
+        assert_eq!(queue.dropped_count(), 1);
+        assert!(queue.pop_blocking(&die_bool).is_some());
+        assert!(queue.pop_blocking(&die_bool).is_some());
+    }
+
+    #[test]
+    fn frame_queue_drop_newest_discards_incoming_frame() {
+        let queue = FrameQueue::new(1, FrameDropPolicy::DropNewest);
+        let die_bool = AtomicBool::new(false);
+        queue.push(sized_test_buffer(1), &die_bool);
+        queue.push(sized_test_buffer(2), &die_bool);
+        assert_eq!(queue.dropped_count(), 1);
+        assert_eq!(queue.pop_blocking(&die_bool).unwrap().resolution(), Resolution::new(1, 1));
+    }
+
+    #[test]
+    fn frame_queue_push_gives_up_once_die_bool_is_set() {
+        let queue = Arc::new(FrameQueue::new(1, FrameDropPolicy::Block));
+        let die_bool = Arc::new(AtomicBool::new(false));
+        queue.push(test_buffer(), &die_bool);
+
+        // The queue is already full, so this would block forever under `Block`
+        // policy if `push` didn't also watch `die_bool` the way `pop_blocking` does.
+        let blocked = queue.clone();
+        let die_bool_thread = die_bool.clone();
+        let pusher = thread::spawn(move || blocked.push(test_buffer(), &die_bool_thread));
+
+        thread::sleep(Duration::from_millis(20));
+        die_bool.store(true, Ordering::SeqCst);
+        queue.wake_all();
+
+        pusher.join().unwrap();
+        // The second frame was dropped on shutdown rather than queued past it.
+        assert_eq!(queue.frames.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn frame_queue_pop_blocking_returns_none_when_dying_and_empty() {
+        let queue = FrameQueue::new(1, FrameDropPolicy::Block);
+        let die_bool = AtomicBool::new(true);
+        assert_eq!(queue.pop_blocking(&die_bool), None);
+    }
+
+    #[test]
+    fn parse_setup_transport_parses_udp_client_port() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = TcpStream::connect(addr).unwrap();
+        let (server_side, _) = listener.accept().unwrap();
+
+        let transport =
+            parse_setup_transport("RTP/AVP;unicast;client_port=5000-5001", &server_side)
+                .expect("udp transport header should parse");
+        match transport {
+            RtspTransport::Udp { remote, .. } => {
+                assert_eq!(remote.port(), 5000);
+                assert_eq!(remote.ip(), client.local_addr().unwrap().ip());
+            }
+            RtspTransport::Interleaved { .. } => panic!("expected a UDP transport"),
+        }
+    }
+
+    #[test]
+    fn parse_setup_transport_parses_interleaved_channel() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _client = TcpStream::connect(addr).unwrap();
+        let (server_side, _) = listener.accept().unwrap();
+
+        let transport = parse_setup_transport("RTP/AVP/TCP;interleaved=0-1", &server_side)
+            .expect("interleaved transport header should parse");
+        match transport {
+            RtspTransport::Interleaved { channel, .. } => assert_eq!(channel, 0),
+            RtspTransport::Udp { .. } => panic!("expected an interleaved transport"),
+        }
+    }
+
+    #[test]
+    fn parse_setup_transport_rejects_missing_or_malformed_header() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _client = TcpStream::connect(addr).unwrap();
+        let (server_side, _) = listener.accept().unwrap();
+
+        assert!(parse_setup_transport("RTP/AVP;unicast", &server_side).is_none());
+        assert!(
+            parse_setup_transport("RTP/AVP;unicast;client_port=notaport", &server_side).is_none()
+        );
+    }
+
+    #[test]
+    fn align_frame_set_keeps_frames_within_tolerance() {
+        let newest = Instant::now();
+        let mut frames = HashMap::new();
+        frames.insert(CameraIndex::Index(0), (newest, test_buffer()));
+        frames.insert(
+            CameraIndex::Index(1),
+            (newest - Duration::from_millis(5), test_buffer()),
+        );
+
+        let aligned = align_frame_set(frames, Duration::from_millis(10));
+        assert_eq!(aligned.len(), 2);
+    }
+
+    #[test]
+    fn align_frame_set_drops_stale_frames_outside_tolerance() {
+        let newest = Instant::now();
+        let mut frames = HashMap::new();
+        frames.insert(CameraIndex::Index(0), (newest, test_buffer()));
+        frames.insert(
+            CameraIndex::Index(1),
+            (newest - Duration::from_millis(500), test_buffer()),
+        );
+
+        let aligned = align_frame_set(frames, Duration::from_millis(10));
+        assert_eq!(aligned.len(), 1);
+        assert!(aligned.contains_key(&CameraIndex::Index(0)));
+    }
+
+    #[test]
+    fn align_frame_set_is_a_no_op_on_an_empty_set() {
+        let frames: HashMap<CameraIndex, (Instant, Buffer)> = HashMap::new();
+        assert!(align_frame_set(frames, Duration::from_millis(10)).is_empty());
+    }
+
+    #[test]
+    fn capture_suspend_guard_sets_flag_while_held_and_clears_on_drop() {
+        let capture_suspended = Arc::new(AtomicBool::new(false));
+        {
+            let _guard = CaptureSuspendGuard::new(&capture_suspended);
+            assert!(capture_suspended.load(Ordering::SeqCst));
+        }
+        assert!(!capture_suspended.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn capture_suspend_guard_clears_flag_if_caller_bails_early() {
+        fn suspend_then_bail(capture_suspended: &Arc<AtomicBool>, bail: bool) -> Result<(), ()> {
+            let _guard = CaptureSuspendGuard::new(capture_suspended);
+            if bail {
+                return Err(());
+            }
+            Ok(())
+        }
+
+        let capture_suspended = Arc::new(AtomicBool::new(false));
+        let _ = suspend_then_bail(&capture_suspended, true);
+        assert!(!capture_suspended.load(Ordering::SeqCst));
+    }
+
+    fn test_camera_info(index: u32) -> CameraInfo {
+        CameraInfo::new("test camera", "", "", CameraIndex::Index(index))
+    }
+
+    #[test]
+    fn diff_known_devices_reports_newly_connected_cameras() {
+        let known = vec![];
+        let current = vec![test_camera_info(0)];
+        let events = diff_known_devices(&known, &current);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], CameraStatusEvent::Connected(_)));
+    }
+
+    #[test]
+    fn diff_known_devices_reports_disconnected_cameras() {
+        let known = vec![test_camera_info(0)];
+        let current = vec![];
+        let events = diff_known_devices(&known, &current);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            events[0],
+            CameraStatusEvent::Disconnected(CameraIndex::Index(0))
+        ));
+    }
+
+    #[test]
+    fn diff_known_devices_reports_both_connect_and_disconnect_in_one_pass() {
+        let known = vec![test_camera_info(0)];
+        let current = vec![test_camera_info(1)];
+        let events = diff_known_devices(&known, &current);
+        assert_eq!(events.len(), 2);
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, CameraStatusEvent::Connected(_))));
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, CameraStatusEvent::Disconnected(_))));
+    }
+
+    #[test]
+    fn diff_known_devices_is_a_no_op_when_unchanged_or_both_empty() {
+        assert!(diff_known_devices(&[], &[]).is_empty());
+        let cameras = vec![test_camera_info(0)];
+        assert!(diff_known_devices(&cameras, &cameras).is_empty());
     }
 }